@@ -1,8 +1,12 @@
 use std::convert::TryInto;
 use std::default::Default;
-use std::mem::forget;
 use std::slice;
 
+#[cfg(feature = "string_encoding")]
+use encoding_rs::CoderResult;
+#[cfg(feature = "string_encoding")]
+pub use encoding_rs::Encoding;
+
 use crate::support::char;
 use crate::support::int;
 use crate::HandleScope;
@@ -39,10 +43,170 @@ extern "C" {
     length: int,
   ) -> *const String;
 
+  fn v8__String__NewFromTwoByte(
+    isolate: *mut Isolate,
+    data: *const u16,
+    new_type: NewStringType,
+    length: int,
+  ) -> *const String;
+
+  fn v8__String__Write(
+    this: *const String,
+    isolate: *mut Isolate,
+    buffer: *mut u16,
+    start: int,
+    length: int,
+    options: WriteOptions,
+  ) -> int;
+
+  fn v8__String__NewExternalOneByte(
+    isolate: *mut Isolate,
+    data: *const u8,
+    length: usize,
+    free_fn: extern "C" fn(*mut u8, usize),
+  ) -> *const String;
+
+  fn v8__String__NewExternalTwoByte(
+    isolate: *mut Isolate,
+    data: *const u16,
+    length: usize,
+    free_fn: extern "C" fn(*mut u16, usize),
+  ) -> *const String;
+
+  fn v8__String__GetExternalOneByteStringResourceData(
+    this: *const String,
+    length: *mut usize,
+  ) -> *const u8;
+
+  fn v8__String__GetExternalStringResourceData(
+    this: *const String,
+    length: *mut usize,
+  ) -> *const u16;
+
   fn v8__String__IsExternal(this: *const String) -> bool;
   fn v8__String__IsExternalOneByte(this: *const String) -> bool;
   fn v8__String__IsExternalTwoByte(this: *const String) -> bool;
   fn v8__String__IsOneByte(this: *const String) -> bool;
+
+  fn v8__String__ValueView__New(
+    isolate: *mut Isolate,
+    string: *const String,
+  ) -> *mut ValueViewOpaque;
+  fn v8__String__ValueView__Delete(this: *mut ValueViewOpaque);
+  fn v8__String__ValueView__IsOneByte(this: *const ValueViewOpaque) -> bool;
+  fn v8__String__ValueView__Data8(this: *const ValueViewOpaque) -> *const u8;
+  fn v8__String__ValueView__Data16(
+    this: *const ValueViewOpaque,
+  ) -> *const u16;
+  fn v8__String__ValueView__Length(this: *const ValueViewOpaque) -> int;
+}
+
+#[repr(C)]
+struct ValueViewOpaque {
+  _private: [u8; 0],
+}
+
+// Wraps a `v8::String::ValueView`, which flattens `string` (if necessary)
+// and exposes a direct pointer into its one-byte or two-byte backing store.
+// Internally, `ValueView` holds a `DisallowGarbageCollection` guard for as
+// long as it is alive: that guard, not the one-time flattening, is what
+// keeps the backing store from being relocated by a compacting GC. So the
+// pointer it hands out is only valid while the `ValueView` itself is still
+// alive — see `OneByteContent`/`TwoByteContent`, which pair the pointer
+// with the `ValueView` that must outlive it.
+struct ValueView(*mut ValueViewOpaque);
+
+impl ValueView {
+  fn new(scope: &mut Isolate, string: &String) -> Self {
+    ValueView(unsafe { v8__String__ValueView__New(scope, string) })
+  }
+
+  fn is_onebyte(&self) -> bool {
+    unsafe { v8__String__ValueView__IsOneByte(self.0) }
+  }
+
+  fn data8(&self) -> *const u8 {
+    unsafe { v8__String__ValueView__Data8(self.0) }
+  }
+
+  fn data16(&self) -> *const u16 {
+    unsafe { v8__String__ValueView__Data16(self.0) }
+  }
+
+  fn length(&self) -> usize {
+    unsafe { v8__String__ValueView__Length(self.0) as usize }
+  }
+}
+
+impl Drop for ValueView {
+  fn drop(&mut self) {
+    unsafe { v8__String__ValueView__Delete(self.0) }
+  }
+}
+
+enum OneByteContentRepr<'s> {
+  External(&'s [u8]),
+  Flat(ValueView),
+}
+
+/// Borrowed one-byte (Latin-1) content returned by
+/// [`String::get_onebyte_content`]. For externally-backed strings this
+/// borrows V8's resource directly; for ordinary (flat) strings it instead
+/// owns the [`v8::String::ValueView`] guard that pins the backing store
+/// against relocation, so the content remains valid for as long as this
+/// value is kept alive.
+pub struct OneByteContent<'s>(OneByteContentRepr<'s>);
+
+impl<'s> std::ops::Deref for OneByteContent<'s> {
+  type Target = [u8];
+
+  fn deref(&self) -> &[u8] {
+    match &self.0 {
+      OneByteContentRepr::External(bytes) => bytes,
+      OneByteContentRepr::Flat(view) => unsafe {
+        slice::from_raw_parts(view.data8(), view.length())
+      },
+    }
+  }
+}
+
+enum TwoByteContentRepr<'s> {
+  External(&'s [u16]),
+  Flat(ValueView),
+}
+
+/// Borrowed two-byte (UTF-16) content returned by
+/// [`String::get_twobyte_content`]. See [`OneByteContent`] for the
+/// borrow/guard distinction between externally-backed and flat strings.
+pub struct TwoByteContent<'s>(TwoByteContentRepr<'s>);
+
+impl<'s> std::ops::Deref for TwoByteContent<'s> {
+  type Target = [u16];
+
+  fn deref(&self) -> &[u16] {
+    match &self.0 {
+      TwoByteContentRepr::External(units) => units,
+      TwoByteContentRepr::Flat(view) => unsafe {
+        slice::from_raw_parts(view.data16(), view.length())
+      },
+    }
+  }
+}
+
+// Trampolines invoked by the C++ `ExternalStringResource`/
+// `ExternalOneByteStringResource` subclasses when V8 disposes of an
+// externalized string, reconstructing and dropping the `Box` that was
+// handed to V8 when the string was created.
+extern "C" fn drop_external_onebyte_string(data: *mut u8, length: usize) {
+  drop(unsafe {
+    Box::from_raw(slice::from_raw_parts_mut(data, length) as *mut [u8])
+  });
+}
+
+extern "C" fn drop_external_twobyte_string(data: *mut u16, length: usize) {
+  drop(unsafe {
+    Box::from_raw(slice::from_raw_parts_mut(data, length) as *mut [u16])
+  });
 }
 
 #[repr(C)]
@@ -102,6 +266,27 @@ impl String {
     }
   }
 
+  pub fn new_from_two_byte<'s>(
+    scope: &mut HandleScope<'s, ()>,
+    buffer: &[u16],
+    new_type: NewStringType,
+  ) -> Option<Local<'s, String>> {
+    if buffer.is_empty() {
+      return Some(Self::empty(scope));
+    }
+    let buffer_len = buffer.len().try_into().ok()?;
+    unsafe {
+      scope.cast_local(|sd| {
+        v8__String__NewFromTwoByte(
+          sd.get_isolate_ptr(),
+          buffer.as_ptr(),
+          new_type,
+          buffer_len,
+        )
+      })
+    }
+  }
+
   /// Returns the number of characters (UTF-16 code units) in this string.
   pub fn length(&self) -> usize {
     unsafe { v8__String__Length(self) as usize }
@@ -137,6 +322,31 @@ impl String {
     bytes as usize
   }
 
+  /// Writes this string's UTF-16 code units into `buffer`, starting at the
+  /// beginning of the string, and returns the number of code units written.
+  /// Unlike [`write_utf8`][Self::write_utf8], V8's `String::Write` already
+  /// reports that count as its return value, so there is no separate
+  /// `nchars_ref` out-parameter to thread through.
+  pub fn write(
+    &self,
+    scope: &mut Isolate,
+    buffer: &mut [u16],
+    options: WriteOptions,
+  ) -> usize {
+    unsafe {
+      v8__String__Write(
+        self,
+        scope,
+        buffer.as_mut_ptr(),
+        0,
+        buffer.len().try_into().unwrap_or(int::max_value()),
+        options,
+      )
+    }
+    .try_into()
+    .unwrap_or(0)
+  }
+
   // Convenience function not present in the original V8 API.
   pub fn new<'s>(
     scope: &mut HandleScope<'s, ()>,
@@ -167,6 +377,63 @@ impl String {
     }
   }
 
+  /// Creates a `v8::String` backed by a Rust-owned one-byte (Latin-1)
+  /// buffer. Unlike [`new_external_onebyte_static`][Self::new_external_onebyte_static],
+  /// `buffer` does not need to be `'static`: V8 calls back into Rust to
+  /// drop it once the string is garbage collected.
+  pub fn new_external_onebyte<'s>(
+    scope: &mut HandleScope<'s, ()>,
+    buffer: Box<[u8]>,
+  ) -> Option<Local<'s, String>> {
+    if buffer.is_empty() {
+      return None;
+    }
+    let length = buffer.len();
+    let data = Box::into_raw(buffer) as *mut u8;
+    let local = unsafe {
+      scope.cast_local(|sd| {
+        v8__String__NewExternalOneByte(
+          sd.get_isolate_ptr(),
+          data,
+          length,
+          drop_external_onebyte_string,
+        )
+      })
+    };
+    if local.is_none() {
+      drop_external_onebyte_string(data, length);
+    }
+    local
+  }
+
+  /// Creates a `v8::String` backed by a Rust-owned two-byte (UTF-16)
+  /// buffer. V8 calls back into Rust to drop `buffer` once the string is
+  /// garbage collected, so it does not need to be `'static` or leaked.
+  pub fn new_external_twobyte<'s>(
+    scope: &mut HandleScope<'s, ()>,
+    buffer: Box<[u16]>,
+  ) -> Option<Local<'s, String>> {
+    if buffer.is_empty() {
+      return None;
+    }
+    let length = buffer.len();
+    let data = Box::into_raw(buffer) as *mut u16;
+    let local = unsafe {
+      scope.cast_local(|sd| {
+        v8__String__NewExternalTwoByte(
+          sd.get_isolate_ptr(),
+          data,
+          length,
+          drop_external_twobyte_string,
+        )
+      })
+    };
+    if local.is_none() {
+      drop_external_twobyte_string(data, length);
+    }
+    local
+  }
+
   /// True if string is external
   pub fn is_external(&self) -> bool {
     // TODO: re-enable on next v8-release
@@ -185,7 +452,7 @@ impl String {
   }
 
   /// True if string is external & two-byte
-  /// NOTE: can't yet be created via rusty_v8
+  /// (e.g: created with new_external_twobyte)
   pub fn is_external_twobyte(&self) -> bool {
     unsafe { v8__String__IsExternalTwoByte(self) }
   }
@@ -193,24 +460,442 @@ impl String {
   /// True if string is known to contain only one-byte data
   /// doesn't read the string so can return false positives
   pub fn is_onebyte(&self) -> bool {
-    unsafe { v8__String__IsExternalOneByte(self) }
+    unsafe { v8__String__IsOneByte(self) }
+  }
+
+  /// Returns a borrowed view of this string's one-byte (Latin-1) content
+  /// without copying, if it is backed by an external one-byte resource (see
+  /// [`is_external_onebyte`][Self::is_external_onebyte]). Any other
+  /// representation returns `None`; use
+  /// [`get_onebyte_content`][Self::get_onebyte_content] to also cover
+  /// ordinary (non-external) one-byte strings.
+  fn get_external_onebyte_content(&self) -> Option<&[u8]> {
+    if !self.is_external_onebyte() {
+      return None;
+    }
+    let mut length: usize = 0;
+    let data = unsafe {
+      v8__String__GetExternalOneByteStringResourceData(self, &mut length)
+    };
+    if data.is_null() {
+      return None;
+    }
+    Some(unsafe { slice::from_raw_parts(data, length) })
+  }
+
+  /// Returns a borrowed view of this string's two-byte (UTF-16) content
+  /// without copying, if it is backed by an external two-byte resource (see
+  /// [`is_external_twobyte`][Self::is_external_twobyte]). Any other
+  /// representation returns `None`; use
+  /// [`get_twobyte_content`][Self::get_twobyte_content] to also cover
+  /// ordinary (non-external) two-byte strings.
+  fn get_external_twobyte_content(&self) -> Option<&[u16]> {
+    if !self.is_external_twobyte() {
+      return None;
+    }
+    let mut length: usize = 0;
+    let data = unsafe {
+      v8__String__GetExternalStringResourceData(self, &mut length)
+    };
+    if data.is_null() {
+      return None;
+    }
+    Some(unsafe { slice::from_raw_parts(data, length) })
   }
 
-  /// Convenience function not present in the original V8 API.
+  /// Returns a borrowed view of this string's one-byte (Latin-1) content
+  /// without copying, combined with [`is_onebyte`][Self::is_onebyte]:
+  /// external one-byte strings are viewed directly (no V8 call needed
+  /// beyond the resource lookup); any other one-byte string is flattened
+  /// via a [`v8::String::ValueView`] guard, which the returned
+  /// [`OneByteContent`] keeps alive for as long as the content is
+  /// borrowed (the guard is what pins the backing store against a
+  /// compacting GC; it does not outlive the `OneByteContent`). Returns
+  /// `None` if the string is not one-byte, or if V8 declines to hand back
+  /// a direct view.
+  pub fn get_onebyte_content<'s>(
+    &'s self,
+    scope: &mut Isolate,
+  ) -> Option<OneByteContent<'s>> {
+    if let Some(bytes) = self.get_external_onebyte_content() {
+      return Some(OneByteContent(OneByteContentRepr::External(bytes)));
+    }
+    if !self.is_onebyte() {
+      return None;
+    }
+    let view = ValueView::new(scope, self);
+    if !view.is_onebyte() || view.data8().is_null() {
+      return None;
+    }
+    Some(OneByteContent(OneByteContentRepr::Flat(view)))
+  }
+
+  /// Returns a borrowed view of this string's two-byte (UTF-16) content
+  /// without copying. Mirrors
+  /// [`get_onebyte_content`][Self::get_onebyte_content]: tries the external
+  /// two-byte resource first, then falls back to flattening via
+  /// [`v8::String::ValueView`] for ordinary two-byte strings, pinned for
+  /// as long as the returned [`TwoByteContent`] is alive.
+  pub fn get_twobyte_content<'s>(
+    &'s self,
+    scope: &mut Isolate,
+  ) -> Option<TwoByteContent<'s>> {
+    if let Some(units) = self.get_external_twobyte_content() {
+      return Some(TwoByteContent(TwoByteContentRepr::External(units)));
+    }
+    if self.is_onebyte() {
+      return None;
+    }
+    let view = ValueView::new(scope, self);
+    if view.is_onebyte() || view.data16().is_null() {
+      return None;
+    }
+    Some(TwoByteContent(TwoByteContentRepr::Flat(view)))
+  }
+
+  /// Convenience function not present in the original V8 API. Invalid UTF-8
+  /// (lone surrogates in the original `String`) is replaced with U+FFFD; see
+  /// [`to_rust_string`][Self::to_rust_string] to detect that instead.
   pub fn to_rust_string_lossy(
     &self,
     scope: &mut Isolate,
   ) -> std::string::String {
     let capacity = self.utf8_length(scope);
-    let mut string = std::string::String::with_capacity(capacity);
-    let data = string.as_mut_ptr();
-    forget(string);
+    let mut buffer: Vec<u8> = Vec::with_capacity(capacity);
     let length = self.write_utf8(
       scope,
-      unsafe { slice::from_raw_parts_mut(data, capacity) },
+      unsafe { slice::from_raw_parts_mut(buffer.as_mut_ptr(), capacity) },
       None,
       WriteOptions::NO_NULL_TERMINATION | WriteOptions::REPLACE_INVALID_UTF8,
     );
-    unsafe { std::string::String::from_raw_parts(data, length, capacity) }
+    unsafe { buffer.set_len(length) };
+    // SAFETY: REPLACE_INVALID_UTF8 guarantees V8 wrote valid UTF-8.
+    unsafe { std::string::String::from_utf8_unchecked(buffer) }
+  }
+
+  /// Like [`to_rust_string_lossy`][Self::to_rust_string_lossy], but reports
+  /// invalid UTF-8 (lone surrogates in the original `String`) as an error
+  /// instead of silently replacing it.
+  pub fn to_rust_string(
+    &self,
+    scope: &mut Isolate,
+  ) -> Result<std::string::String, std::str::Utf8Error> {
+    let capacity = self.utf8_length(scope);
+    let mut buffer: Vec<u8> = Vec::with_capacity(capacity);
+    let length = self.write_utf8(
+      scope,
+      unsafe { slice::from_raw_parts_mut(buffer.as_mut_ptr(), capacity) },
+      None,
+      WriteOptions::NO_NULL_TERMINATION,
+    );
+    unsafe { buffer.set_len(length) };
+    std::string::String::from_utf8(buffer).map_err(|e| e.utf8_error())
+  }
+
+  /// Like [`to_rust_string_lossy`][Self::to_rust_string_lossy], but borrows
+  /// directly into V8's backing store, without allocating, when this
+  /// string is backed by an external one-byte resource (see
+  /// [`is_external_onebyte`][Self::is_external_onebyte]) and is pure
+  /// ASCII. Ordinary (flat) one-byte strings still take the borrow-free
+  /// path when reading out their content, but are copied into an owned
+  /// `String` before returning: unlike an external resource, a flat
+  /// string's backing store is only pinned against GC relocation for as
+  /// long as the `v8::String::ValueView` guard behind
+  /// [`get_onebyte_content`][Self::get_onebyte_content] is alive, which
+  /// can't extend past this function's return.
+  pub fn to_rust_cow_lossy(
+    &self,
+    scope: &mut Isolate,
+  ) -> std::borrow::Cow<'_, str> {
+    if let Some(content) = self.get_onebyte_content(scope) {
+      if let OneByteContentRepr::External(bytes) = content.0 {
+        if bytes.is_ascii() {
+          // SAFETY: ASCII bytes are valid UTF-8.
+          return std::borrow::Cow::Borrowed(unsafe {
+            std::str::from_utf8_unchecked(bytes)
+          });
+        }
+      } else if content.is_ascii() {
+        // SAFETY: ASCII bytes are valid UTF-8.
+        let s = unsafe { std::str::from_utf8_unchecked(&content) };
+        return std::borrow::Cow::Owned(s.to_owned());
+      }
+    }
+    std::borrow::Cow::Owned(self.to_rust_string_lossy(scope))
+  }
+
+  /// Decodes `bytes` from the given WHATWG `encoding` and creates a new
+  /// `v8::String` from the result, following the Encoding Standard's decode
+  /// algorithm without BOM sniffing (malformed sequences become U+FFFD; a
+  /// leading BOM is kept as U+FEFF). Use
+  /// [`encoding_rs::UTF_8`], `encoding_rs::WINDOWS_1252`, etc. as `encoding`.
+  ///
+  /// Requires the `string_encoding` feature.
+  #[cfg(feature = "string_encoding")]
+  pub fn new_from_encoded<'s>(
+    scope: &mut HandleScope<'s, ()>,
+    bytes: &[u8],
+    encoding: &'static Encoding,
+    new_type: NewStringType,
+  ) -> Option<Local<'s, String>> {
+    if encoding == encoding_rs::UTF_8 {
+      return Self::new_from_utf8(scope, bytes, new_type);
+    }
+    let units = decode_to_utf16(bytes, encoding)?;
+    Self::new_from_two_byte(scope, &units, new_type)
+  }
+
+  /// Encodes this string's content into the given WHATWG `encoding`,
+  /// substituting a numeric character reference for codepoints `encoding`
+  /// cannot represent, per the Encoding Standard's encode algorithm. For
+  /// UTF-16LE, UTF-16BE and replacement, which have no encoder of their own,
+  /// the output is UTF-8 (the encoding's `output_encoding()`).
+  ///
+  /// Requires the `string_encoding` feature.
+  #[cfg(feature = "string_encoding")]
+  pub fn to_encoded(
+    &self,
+    scope: &mut Isolate,
+    encoding: &'static Encoding,
+  ) -> Vec<u8> {
+    if encoding == encoding_rs::UTF_8 {
+      return self.to_rust_string_lossy(scope).into_bytes();
+    }
+    let len = self.length();
+    let mut units = vec![0u16; len];
+    self.write(scope, &mut units, WriteOptions::NO_NULL_TERMINATION);
+    let mut encoder = encoding.new_encoder();
+    let mut out = Vec::with_capacity(units.len());
+    let mut read = 0;
+    loop {
+      let (result, consumed, _had_errors) =
+        encoder.encode_from_utf16_to_vec(&units[read..], &mut out, true);
+      read += consumed;
+      match result {
+        CoderResult::InputEmpty => break,
+        CoderResult::OutputFull => out.reserve(units.len()),
+      }
+    }
+    out
+  }
+
+  /// Creates a `v8::String` from WTF-8 encoded `buffer`. WTF-8 is a
+  /// superset of UTF-8 that additionally allows encoding lone (unpaired)
+  /// surrogate code points, letting potentially-ill-formed UTF-16 `String`
+  /// content round-trip through Rust losslessly.
+  pub fn new_from_wtf8<'s>(
+    scope: &mut HandleScope<'s, ()>,
+    buffer: &[u8],
+    new_type: NewStringType,
+  ) -> Option<Local<'s, String>> {
+    let units = wtf8_to_utf16(buffer);
+    Self::new_from_two_byte(scope, &units, new_type)
+  }
+
+  /// Writes this string's content into `buffer` as WTF-8, returning the
+  /// number of bytes written. Unlike [`write_utf8`][Self::write_utf8], lone
+  /// surrogates are preserved rather than replaced. If `buffer` is too small
+  /// to hold the whole string, writing stops at the last code point (or
+  /// surrogate pair) that fully fits, rather than emitting a truncated,
+  /// invalid WTF-8 sequence; `nchars_ref`, if given, is set to the number of
+  /// UTF-16 code units actually consumed, which may be less than
+  /// [`length`][Self::length].
+  pub fn write_wtf8(
+    &self,
+    scope: &mut Isolate,
+    buffer: &mut [u8],
+    nchars_ref: Option<&mut usize>,
+  ) -> usize {
+    let len = self.length();
+    let mut units = vec![0u16; len];
+    self.write(scope, &mut units, WriteOptions::NO_NULL_TERMINATION);
+    let (written, consumed) = utf16_to_wtf8_bounded(&units, buffer);
+    if let Some(r) = nchars_ref {
+      *r = consumed;
+    }
+    written
+  }
+}
+
+// Decodes `bytes` from `encoding` into UTF-16 code units, following the
+// Encoding Standard's decode algorithm without BOM sniffing (malformed
+// sequences become U+FFFD; a leading BOM is kept as U+FEFF). Returns `None` only if `bytes` is implausibly large for `usize`.
+#[cfg(feature = "string_encoding")]
+fn decode_to_utf16(bytes: &[u8], encoding: &'static Encoding) -> Option<Vec<u16>> {
+  let mut decoder = encoding.new_decoder_without_bom_handling();
+  let mut units = vec![0u16; decoder.max_utf16_buffer_length(bytes.len())?];
+  let (_result, _read, written, _had_errors) =
+    decoder.decode_to_utf16(bytes, &mut units, true);
+  units.truncate(written);
+  Some(units)
+}
+
+// Encodes a single Unicode (or lone-surrogate) code point as WTF-8, mirroring
+// `char::encode_utf8` except that it also accepts code points in the
+// surrogate range (U+D800-U+DFFF), which `char` cannot represent.
+fn encode_wtf8_codepoint(code: u32, buf: &mut [u8; 4]) -> usize {
+  if code < 0x80 {
+    buf[0] = code as u8;
+    1
+  } else if code < 0x800 {
+    buf[0] = 0xC0 | (code >> 6) as u8;
+    buf[1] = 0x80 | (code & 0x3F) as u8;
+    2
+  } else if code < 0x1_0000 {
+    buf[0] = 0xE0 | (code >> 12) as u8;
+    buf[1] = 0x80 | ((code >> 6) & 0x3F) as u8;
+    buf[2] = 0x80 | (code & 0x3F) as u8;
+    3
+  } else {
+    buf[0] = 0xF0 | (code >> 18) as u8;
+    buf[1] = 0x80 | ((code >> 12) & 0x3F) as u8;
+    buf[2] = 0x80 | ((code >> 6) & 0x3F) as u8;
+    buf[3] = 0x80 | (code & 0x3F) as u8;
+    4
+  }
+}
+
+// Converts UTF-16 code units (which may include unpaired surrogates) into
+// WTF-8 bytes, writing as many whole code points as fit into `buffer` and
+// returning `(bytes_written, units_consumed)`. Never writes a partial
+// multi-byte sequence, so the written prefix is always valid WTF-8.
+fn utf16_to_wtf8_bounded(units: &[u16], buffer: &mut [u8]) -> (usize, usize) {
+  let mut buf = [0u8; 4];
+  let mut iter = units.iter().copied().peekable();
+  let mut written = 0;
+  let mut consumed = 0;
+  while let Some(unit) = iter.next() {
+    let (code, unit_count) = if (0xD800..=0xDBFF).contains(&unit) {
+      match iter.peek() {
+        Some(&low) if (0xDC00..=0xDFFF).contains(&low) => {
+          iter.next();
+          (
+            0x1_0000
+              + ((unit as u32 - 0xD800) << 10)
+              + (low as u32 - 0xDC00),
+            2,
+          )
+        }
+        _ => (unit as u32, 1),
+      }
+    } else {
+      (unit as u32, 1)
+    };
+    let n = encode_wtf8_codepoint(code, &mut buf);
+    if written + n > buffer.len() {
+      break;
+    }
+    buffer[written..written + n].copy_from_slice(&buf[..n]);
+    written += n;
+    consumed += unit_count;
+  }
+  (written, consumed)
+}
+
+// Decodes WTF-8 `bytes` into UTF-16 code units. A high surrogate encoded as
+// a 3-byte sequence immediately followed by a low surrogate's 3-byte
+// sequence naturally forms a valid surrogate pair once pushed, matching
+// what a single 4-byte supplementary-codepoint sequence would have produced.
+fn wtf8_to_utf16(bytes: &[u8]) -> Vec<u16> {
+  let mut units = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    let b0 = bytes[i];
+    let (code, len) = if b0 < 0x80 {
+      (b0 as u32, 1)
+    } else if b0 & 0xE0 == 0xC0 && i + 1 < bytes.len() {
+      (((b0 as u32 & 0x1F) << 6) | (bytes[i + 1] as u32 & 0x3F), 2)
+    } else if b0 & 0xF0 == 0xE0 && i + 2 < bytes.len() {
+      (
+        ((b0 as u32 & 0x0F) << 12)
+          | ((bytes[i + 1] as u32 & 0x3F) << 6)
+          | (bytes[i + 2] as u32 & 0x3F),
+        3,
+      )
+    } else if b0 & 0xF8 == 0xF0 && i + 3 < bytes.len() {
+      (
+        ((b0 as u32 & 0x07) << 18)
+          | ((bytes[i + 1] as u32 & 0x3F) << 12)
+          | ((bytes[i + 2] as u32 & 0x3F) << 6)
+          | (bytes[i + 3] as u32 & 0x3F),
+        4,
+      )
+    } else {
+      (0xFFFD, 1)
+    };
+    if code >= 0x1_0000 {
+      let c = code - 0x1_0000;
+      units.push(0xD800 + (c >> 10) as u16);
+      units.push(0xDC00 + (c & 0x3FF) as u16);
+    } else {
+      units.push(code as u16);
+    }
+    i += len;
+  }
+  units
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn wtf8_round_trips_lone_surrogates() {
+    // A lone high surrogate, a lone low surrogate, and a valid surrogate
+    // pair (U+1F600) sandwiched between ASCII; the two lone surrogates
+    // would not survive a strict UTF-8 round-trip.
+    let units: Vec<u16> = vec![
+      'a' as u16, 0xD800, 'b' as u16, 0xDC00, 'c' as u16, 0xD83D, 0xDE00,
+    ];
+    let mut buffer = [0u8; 64];
+    let (written, consumed) = utf16_to_wtf8_bounded(&units, &mut buffer);
+    assert_eq!(consumed, units.len());
+    assert_eq!(wtf8_to_utf16(&buffer[..written]), units);
+  }
+
+  #[test]
+  fn write_wtf8_bounded_never_splits_a_multibyte_sequence() {
+    // U+1F600 encodes as a 4-byte WTF-8 sequence; a 3-byte buffer can't fit
+    // it, so nothing past the preceding ASCII byte should be written.
+    let units: Vec<u16> = vec!['a' as u16, 0xD83D, 0xDE00];
+    let mut buffer = [0u8; 3];
+    let (written, consumed) = utf16_to_wtf8_bounded(&units, &mut buffer);
+    assert_eq!(written, 1);
+    assert_eq!(consumed, 1);
+    assert_eq!(&buffer[..written], b"a");
+  }
+
+  #[test]
+  fn external_string_dispose_trampolines_free_the_boxed_buffer() {
+    // Exercises the trampolines `v8::String::ExternalOneByteStringResource`/
+    // `ExternalStringResource::Dispose()` call back into on GC, reconstructing
+    // the `Box` handed to V8 by `new_external_onebyte`/`new_external_twobyte`.
+    let onebyte: Box<[u8]> = vec![1u8, 2, 3].into_boxed_slice();
+    let (ptr, len) = (Box::into_raw(onebyte) as *mut u8, 3);
+    drop_external_onebyte_string(ptr, len);
+
+    let twobyte: Box<[u16]> = vec![1u16, 2, 3].into_boxed_slice();
+    let (ptr, len) = (Box::into_raw(twobyte) as *mut u16, 3);
+    drop_external_twobyte_string(ptr, len);
+  }
+
+  #[cfg(feature = "string_encoding")]
+  #[test]
+  fn decode_to_utf16_decodes_non_utf8_legacy_encoding() {
+    // "café" in windows-1252: the trailing 'é' is a single byte (0xE9)
+    // rather than UTF-8's two-byte encoding.
+    let bytes = b"caf\xe9";
+    let units = decode_to_utf16(bytes, encoding_rs::WINDOWS_1252).unwrap();
+    assert_eq!(units, vec!['c' as u16, 'a' as u16, 'f' as u16, 'é' as u16]);
+  }
+
+  #[cfg(feature = "string_encoding")]
+  #[test]
+  fn decode_to_utf16_replaces_malformed_sequences() {
+    // Shift_JIS has no valid interpretation for a lone trailing 0x81 lead
+    // byte; it should be replaced with U+FFFD rather than rejected.
+    let units =
+      decode_to_utf16(b"\x81", encoding_rs::SHIFT_JIS).unwrap();
+    assert_eq!(units, vec![0xFFFD]);
   }
 }